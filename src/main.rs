@@ -1,19 +1,25 @@
 #![allow(clippy::let_unit_value)]
 
-use anyhow::bail;
+use anyhow::{bail, Context as _};
 use bytes::{Buf as _, Bytes};
 use clap::Parser;
 use rayon::{ThreadPool, ThreadPoolBuilder};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use std::fs;
 use std::io::{self, ErrorKind, Write};
 use std::num::NonZeroUsize;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tar::EntryType;
 use target_triple::target;
+use tokio::io::AsyncWriteExt as _;
 use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::sync::broadcast;
 use url::Url;
 
 #[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
@@ -24,46 +30,374 @@ const USER_AGENT: &str = concat!("dtolnay/fast-rustup/v", env!("CARGO_PKG_VERSIO
 const RUSTUP_DIST_SERVER: &str = "https://static.rust-lang.org";
 const TARGET: &str = target!();
 
-struct Component {
-    archive: &'static str,
-    subdir: &'static str,
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+/// Per-mirror attempt budget when there's only one dist-server configured
+/// and so nowhere to fail over to.
+const RETRY_MAX_ATTEMPTS: u32 = 8;
+/// Per-mirror attempt budget when more than one dist-server is configured.
+/// Kept low so a dead mirror is abandoned in a couple of backoff steps
+/// (well under a second, on average) instead of burning through the full
+/// `RETRY_MAX_ATTEMPTS` exponential schedule (tens of seconds) before
+/// `download_component_with_failover` ever gets a chance to try the next one.
+const RETRY_MAX_ATTEMPTS_WITH_FAILOVER: u32 = 2;
+const CIRCUIT_BREAKER_THRESHOLD: usize = 6;
+const SIZE_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A manifest package we know how to install. `subdir` is the directory
+/// one level below the top-level directory of that package's archive, which
+/// for most components is fixed but for `rust-std` is qualified by the
+/// target it was built for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pkg {
+    Cargo,
+    Clippy,
+    RustDocs,
+    RustSrc,
+    RustStd,
+    Rustc,
+    Rustfmt,
 }
 
-const COMPONENTS: &[Component] = &[
-    Component {
-        archive: concat!("cargo-nightly-", target!(), ".tar.xz"),
-        subdir: "cargo",
-    },
-    Component {
-        archive: concat!("clippy-nightly-", target!(), ".tar.xz"),
-        subdir: "clippy-preview",
-    },
-    Component {
-        archive: concat!("rust-docs-nightly-", target!(), ".tar.xz"),
-        subdir: "rust-docs",
-    },
-    Component {
-        archive: concat!("rust-std-nightly-", target!(), ".tar.xz"),
-        subdir: concat!("rust-std-", target!()),
-    },
-    Component {
-        archive: concat!("rustc-nightly-", target!(), ".tar.xz"),
-        subdir: "rustc",
-    },
-    Component {
-        archive: concat!("rustfmt-nightly-", target!(), ".tar.xz"),
-        subdir: "rustfmt-preview",
-    },
-];
+impl Pkg {
+    fn name(self) -> &'static str {
+        match self {
+            Pkg::Cargo => "cargo",
+            Pkg::Clippy => "clippy",
+            Pkg::RustDocs => "rust-docs",
+            Pkg::RustSrc => "rust-src",
+            Pkg::RustStd => "rust-std",
+            Pkg::Rustc => "rustc",
+            Pkg::Rustfmt => "rustfmt",
+        }
+    }
+
+    /// The key this package is filed under in the channel manifest's
+    /// top-level `[pkg.*]` table, which for `clippy` and `rustfmt` is not
+    /// the same as their `--component`/`name()` spelling: both have shipped
+    /// as previews since their manifest entries were created and so are
+    /// still keyed `clippy-preview`/`rustfmt-preview` there.
+    fn manifest_name(self) -> &'static str {
+        match self {
+            Pkg::Clippy => "clippy-preview",
+            Pkg::Rustfmt => "rustfmt-preview",
+            _ => self.name(),
+        }
+    }
+
+    fn from_name(name: &str) -> anyhow::Result<Pkg> {
+        match name {
+            "cargo" => Ok(Pkg::Cargo),
+            "clippy" => Ok(Pkg::Clippy),
+            "rust-docs" => Ok(Pkg::RustDocs),
+            "rust-src" => Ok(Pkg::RustSrc),
+            "rust-std" => Ok(Pkg::RustStd),
+            "rustc" => Ok(Pkg::Rustc),
+            "rustfmt" => Ok(Pkg::Rustfmt),
+            _ => bail!(
+                "{name:?}: unknown component, expected one of \
+                 cargo, clippy, rust-docs, rust-src, rust-std, rustc, rustfmt",
+            ),
+        }
+    }
+
+    fn subdir(self, target: &str) -> String {
+        match self {
+            Pkg::Cargo => "cargo".to_owned(),
+            Pkg::Clippy => "clippy-preview".to_owned(),
+            Pkg::RustDocs => "rust-docs".to_owned(),
+            Pkg::RustSrc => "rust-src".to_owned(),
+            Pkg::RustStd => format!("rust-std-{target}"),
+            Pkg::Rustc => "rustc".to_owned(),
+            Pkg::Rustfmt => "rustfmt-preview".to_owned(),
+        }
+    }
+
+    /// `rust-std` is the only component that is built per-target; every
+    /// other component only ever runs as, and targets, the host.
+    fn is_per_target(self) -> bool {
+        matches!(self, Pkg::RustStd)
+    }
+
+    /// The key an install's target is looked up under in the manifest's
+    /// `[pkg.<name>.target.*]` table. Every component except `rust-src` is
+    /// keyed by the actual target triple it was built for (the host, or an
+    /// extra `rust-std` cross-compilation target); `rust-src` ships one
+    /// source tarball that isn't built for any particular target at all, so
+    /// the manifest files it under the literal wildcard key `"*"` instead.
+    fn manifest_target_key(self, target: &str) -> &str {
+        match self {
+            Pkg::RustSrc => "*",
+            _ => target,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Profile {
+    Minimal,
+    Default,
+    Complete,
+}
+
+impl Profile {
+    fn components(self) -> &'static [Pkg] {
+        match self {
+            Profile::Minimal => &[Pkg::Rustc, Pkg::RustStd, Pkg::Cargo],
+            Profile::Default => &[
+                Pkg::Cargo,
+                Pkg::Clippy,
+                Pkg::RustDocs,
+                Pkg::RustStd,
+                Pkg::Rustc,
+                Pkg::Rustfmt,
+            ],
+            // Same as `default`, plus the standard library's own source, so
+            // IDEs and `#[track_caller]`-style debugging can jump into it;
+            // this is the component rustup's own "complete" profile adds.
+            Profile::Complete => &[
+                Pkg::Cargo,
+                Pkg::Clippy,
+                Pkg::RustDocs,
+                Pkg::RustSrc,
+                Pkg::RustStd,
+                Pkg::Rustc,
+                Pkg::Rustfmt,
+            ],
+        }
+    }
+}
+
+/// One component to install: which package, and which target it was built
+/// for (only ever non-host for an extra `rust-std` cross-compilation target).
+struct Install {
+    pkg: Pkg,
+    target: String,
+    subdir: String,
+}
+
+enum Channel {
+    Nightly(String),
+    Beta,
+    Stable,
+    Version(String),
+}
+
+impl Channel {
+    fn parse(s: &str) -> anyhow::Result<Channel> {
+        if let Some(date) = s.strip_prefix("nightly-") {
+            let bytes = date.as_bytes();
+            if date.len() == "2024-01-01".len()
+                && bytes[0..4].iter().all(u8::is_ascii_digit)
+                && bytes[4] == b'-'
+                && bytes[5..7].iter().all(u8::is_ascii_digit)
+                && bytes[7] == b'-'
+                && bytes[8..10].iter().all(u8::is_ascii_digit)
+            {
+                return Ok(Channel::Nightly(date.to_owned()));
+            }
+            bail!("{s:?}: expected a nightly version in the form \"nightly-2024-01-01\"");
+        }
+        if s == "beta" {
+            return Ok(Channel::Beta);
+        }
+        if s == "stable" {
+            return Ok(Channel::Stable);
+        }
+        let mut parts = s.split('.');
+        if matches!((parts.next(), parts.next(), parts.next(), parts.next()), (Some(a), Some(b), Some(c), None)
+            if !a.is_empty() && !b.is_empty() && !c.is_empty()
+                && a.bytes().all(|b| b.is_ascii_digit())
+                && b.bytes().all(|b| b.is_ascii_digit())
+                && c.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Ok(Channel::Version(s.to_owned()));
+        }
+        bail!(
+            "{s:?}: expected \"nightly-YYYY-MM-DD\", \"beta\", \"stable\", or a release version like \"1.75.0\"",
+        );
+    }
+
+    /// The manifest is always fetched from the primary (first-configured)
+    /// dist server; only the bulk archive downloads are fanned out across
+    /// every `--dist-server` mirror.
+    fn manifest_url(&self, dist_server: &Url) -> String {
+        let dist_server = dist_server.as_str().trim_end_matches('/');
+        match self {
+            Channel::Nightly(date) => {
+                format!("{dist_server}/dist/{date}/channel-rust-nightly.toml")
+            }
+            Channel::Beta => format!("{dist_server}/dist/channel-rust-beta.toml"),
+            Channel::Stable => format!("{dist_server}/dist/channel-rust-stable.toml"),
+            Channel::Version(version) => {
+                format!("{dist_server}/dist/channel-rust-{version}.toml")
+            }
+        }
+    }
+
+    fn toolchain_name(&self) -> String {
+        match self {
+            Channel::Nightly(date) => format!("nightly-{date}"),
+            Channel::Beta => "beta".to_owned(),
+            Channel::Stable => "stable".to_owned(),
+            Channel::Version(version) => version.clone(),
+        }
+    }
+}
 
 #[derive(clap::Parser)]
 #[command(version, author)]
 struct Cli {
     #[arg(
-        value_name = "nightly-2024-01-01",
-        default_value = "nightly-2024-01-01"
+        value_name = "CHANNEL",
+        default_value = "nightly-2024-01-01",
+        help = "nightly-YYYY-MM-DD, beta, stable, or a release like 1.75.0"
     )]
-    nightly: String,
+    channel: String,
+
+    #[arg(long, value_enum, default_value_t = Profile::Default)]
+    profile: Profile,
+
+    /// Install only these components instead of the profile's default set.
+    #[arg(long = "component", value_name = "NAME")]
+    components: Vec<String>,
+
+    /// Extra targets to install `rust-std` for, in addition to the host.
+    #[arg(long = "target", value_name = "TRIPLE")]
+    targets: Vec<String>,
+
+    /// Which archive format to fetch and decompress. "auto" prefers the
+    /// faster-to-decode zstd archive when the manifest offers one.
+    #[arg(long, value_enum, default_value_t = CompressionPref::Auto)]
+    compression: CompressionPref,
+
+    /// Bind a Unix socket (or "host:port" for TCP) and stream
+    /// newline-delimited JSON progress events to anything that connects.
+    #[arg(long, value_name = "path|addr")]
+    status_socket: Option<String>,
+
+    /// Base URL(s) to fetch the manifest and component archives from,
+    /// e.g. "https://static.rust-lang.org". Repeat to list several mirrors;
+    /// the component downloads are distributed round-robin across them
+    /// and fail over to the next mirror if one errors out. Defaults to
+    /// `$RUSTUP_DIST_SERVER` (comma-separated) if set, otherwise the
+    /// upstream dist server.
+    #[arg(long = "dist-server", value_name = "URL")]
+    dist_servers: Vec<String>,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum CompressionPref {
+    Xz,
+    Zstd,
+    Auto,
+}
+
+#[derive(Clone, Copy)]
+enum Compression {
+    Xz,
+    Zstd,
+}
+
+/// Where `--status-socket` should listen: a filesystem path for a Unix
+/// socket, or a `host:port` pair for TCP. We distinguish the two just by
+/// trying to parse the string as a socket address first.
+enum StatusSocketAddr {
+    Unix(std::path::PathBuf),
+    Tcp(std::net::SocketAddr),
+}
+
+impl StatusSocketAddr {
+    fn parse(s: &str) -> StatusSocketAddr {
+        match s.parse() {
+            Ok(addr) => StatusSocketAddr::Tcp(addr),
+            Err(_) => StatusSocketAddr::Unix(std::path::PathBuf::from(s)),
+        }
+    }
+}
+
+/// One line of the newline-delimited JSON progress stream written to every
+/// client connected to `--status-socket`.
+#[derive(serde::Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum StatusEvent {
+    Downloaded {
+        component: String,
+        bytes_downloaded: u64,
+    },
+    ExtractionStarted {
+        component: String,
+    },
+    ExtractionFinished {
+        component: String,
+    },
+    /// Overall progress, in percent. Weighted by bytes downloaded across
+    /// every archive when each one's size could be found via a `HEAD`
+    /// request (see `ProgressTracker`); otherwise approximated as completed
+    /// components over total components.
+    Progress {
+        percent: f64,
+    },
+}
+
+/// Broadcasts `StatusEvent`s, serialized to a JSON line, to every client
+/// connected to `--status-socket`. Only created when the flag is passed;
+/// emitting is a no-op while no client is connected.
+struct StatusSocket {
+    events: broadcast::Sender<String>,
+}
+
+impl StatusSocket {
+    fn emit(&self, event: &StatusEvent) {
+        if self.events.receiver_count() == 0 {
+            return;
+        }
+        if let Ok(line) = serde_json::to_string(event) {
+            let _ = self.events.send(line);
+        }
+    }
+}
+
+async fn bind_status_socket(addr: StatusSocketAddr) -> anyhow::Result<Arc<StatusSocket>> {
+    let (sender, _receiver) = broadcast::channel(1024);
+    let status = Arc::new(StatusSocket { events: sender });
+
+    match addr {
+        StatusSocketAddr::Unix(path) => {
+            let _ = fs::remove_file(&path);
+            let listener = tokio::net::UnixListener::bind(&path)?;
+            tokio::spawn(accept_status_clients_unix(listener, Arc::clone(&status)));
+        }
+        StatusSocketAddr::Tcp(addr) => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            tokio::spawn(accept_status_clients_tcp(listener, Arc::clone(&status)));
+        }
+    }
+
+    Ok(status)
+}
+
+async fn forward_status_events(
+    mut stream: impl tokio::io::AsyncWrite + Unpin,
+    mut receiver: broadcast::Receiver<String>,
+) {
+    while let Ok(line) = receiver.recv().await {
+        if stream.write_all(line.as_bytes()).await.is_err() || stream.write_all(b"\n").await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn accept_status_clients_unix(listener: tokio::net::UnixListener, status: Arc<StatusSocket>) {
+    while let Ok((stream, _addr)) = listener.accept().await {
+        tokio::spawn(forward_status_events(stream, status.events.subscribe()));
+    }
+}
+
+async fn accept_status_clients_tcp(listener: tokio::net::TcpListener, status: Arc<StatusSocket>) {
+    while let Ok((stream, _addr)) = listener.accept().await {
+        tokio::spawn(forward_status_events(stream, status.events.subscribe()));
+    }
 }
 
 fn main() -> anyhow::Result<()> {
@@ -78,43 +412,113 @@ fn main() -> anyhow::Result<()> {
 
 fn do_main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    let date = if cli.nightly.starts_with("nightly-")
-        && cli.nightly.len() == "nightly-2024-01-01".len()
-        && cli.nightly[8..12].bytes().all(|b| b.is_ascii_digit())
-        && cli.nightly[12..13] == *"-"
-        && cli.nightly[13..15].bytes().all(|b| b.is_ascii_digit())
-        && cli.nightly[15..16] == *"-"
-        && cli.nightly[16..18].bytes().all(|b| b.is_ascii_digit())
-    {
-        &cli.nightly["nightly-".len()..]
+    let channel = Channel::parse(&cli.channel)?;
+
+    let pkgs = if cli.components.is_empty() {
+        cli.profile.components().to_vec()
     } else {
-        bail!(
-            "{:?}: expected a nightly version in the form \"nightly-2024-01-01\"",
-            cli.nightly,
-        );
+        cli.components
+            .iter()
+            .map(|name| Pkg::from_name(name))
+            .collect::<anyhow::Result<Vec<Pkg>>>()?
     };
 
+    let mut extra_targets = Vec::new();
+    for target in &cli.targets {
+        if target != TARGET && !extra_targets.contains(target) {
+            extra_targets.push(target.clone());
+        }
+    }
+
+    let mut installs = Vec::new();
+    for pkg in pkgs {
+        if pkg.is_per_target() {
+            installs.push(Install {
+                pkg,
+                target: TARGET.to_owned(),
+                subdir: pkg.subdir(TARGET),
+            });
+            for target in &extra_targets {
+                installs.push(Install {
+                    pkg,
+                    target: target.clone(),
+                    subdir: pkg.subdir(target),
+                });
+            }
+        } else {
+            installs.push(Install {
+                pkg,
+                target: TARGET.to_owned(),
+                subdir: pkg.subdir(TARGET),
+            });
+        }
+    }
+
+    let dist_servers = resolve_dist_servers(&cli.dist_servers)?;
+
     let mut root = home::rustup_home()?;
     create_dir_if_not_exists(&root)?;
     root.push("toolchains");
     create_dir_if_not_exists(&root)?;
-    root.push(format!("nightly-{date}-{TARGET}"));
-    if root.try_exists()? {
+    root.push(format!("{}-{TARGET}", channel.toolchain_name()));
+    let extending_existing_toolchain = root.try_exists()?;
+    if extending_existing_toolchain && cli.components.is_empty() {
         bail!("toolchain already exists: {}", root.display());
     }
 
-    let _ = writeln!(io::stderr(), "Downloading nightly-{date} for {TARGET}");
+    let _ = writeln!(
+        io::stderr(),
+        "Downloading {} for {TARGET}",
+        channel.toolchain_name(),
+    );
 
     let num_threads = thread::available_parallelism()
         .map_or(1, NonZeroUsize::get)
-        .min(COMPONENTS.len());
+        .min(installs.len());
     let thread_pool = ThreadPoolBuilder::new().num_threads(num_threads).build()?;
 
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()?;
 
-    rt.block_on(do_install(thread_pool, date, &root))
+    let status_socket = cli.status_socket.as_deref().map(StatusSocketAddr::parse);
+
+    rt.block_on(do_install(
+        thread_pool,
+        &channel,
+        installs,
+        cli.compression,
+        status_socket,
+        dist_servers,
+        &root,
+    ))
+}
+
+/// Resolves the ordered list of dist-server mirrors: `--dist-server`
+/// (repeatable) takes precedence, falling back to a comma-separated
+/// `RUSTUP_DIST_SERVER` environment variable, falling back to the upstream
+/// default. Every candidate is parsed as a `Url` here so a typo is reported
+/// immediately instead of surfacing mid-download.
+fn resolve_dist_servers(cli_dist_servers: &[String]) -> anyhow::Result<Vec<Url>> {
+    let raw: Vec<String> = if !cli_dist_servers.is_empty() {
+        cli_dist_servers.to_vec()
+    } else if let Ok(env) = std::env::var("RUSTUP_DIST_SERVER") {
+        env.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_owned)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let raw = if raw.is_empty() {
+        vec![RUSTUP_DIST_SERVER.to_owned()]
+    } else {
+        raw
+    };
+    raw.iter()
+        .map(|s| Url::parse(s).with_context(|| format!("{s:?}: invalid --dist-server URL")))
+        .collect()
 }
 
 fn create_dir_if_not_exists(path: &Path) -> io::Result<()> {
@@ -124,6 +528,138 @@ fn create_dir_if_not_exists(path: &Path) -> io::Result<()> {
     }
 }
 
+/// A sibling of `root` to extract into before anything is committed to the
+/// real toolchain directory, named so a crashed run's leftovers are obvious
+/// and won't collide with a concurrent `fast-rustup` process.
+fn staging_path_for(root: &Path) -> std::path::PathBuf {
+    let mut file_name = root.file_name().expect("root has a file name").to_owned();
+    file_name.push(format!(".partial-{}", std::process::id()));
+    root.with_file_name(file_name)
+}
+
+/// Moves every entry under `src` into `dst`, recursing into subdirectories
+/// that already exist on both sides instead of replacing them outright, so
+/// that extending an existing toolchain with extra components only adds the
+/// new files without disturbing the ones already there.
+fn merge_dir_into(src: &Path, dst: &Path) -> io::Result<()> {
+    create_dir_if_not_exists(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            merge_dir_into(&src_path, &dst_path)?;
+        } else {
+            fs::rename(&src_path, &dst_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Subset of the `channel-rust-*.toml` manifest that we care about: for
+/// each package, the per-target `xz_url`/`xz_hash` (and `zst_url`/`zst_hash`
+/// when present) used to fetch and verify that package's archive.
+#[derive(serde::Deserialize)]
+struct Manifest {
+    pkg: BTreeMap<String, ManifestPackage>,
+}
+
+#[derive(serde::Deserialize)]
+struct ManifestPackage {
+    target: BTreeMap<String, ManifestTarget>,
+}
+
+#[derive(serde::Deserialize)]
+struct ManifestTarget {
+    /// Most targets in a real channel manifest are `available = false` with
+    /// none of the fields below present at all (the package just wasn't
+    /// built for that target on this channel); only check the URLs/hashes
+    /// once this is confirmed `true`.
+    available: bool,
+    xz_url: Option<String>,
+    xz_hash: Option<String>,
+    zst_url: Option<String>,
+    zst_hash: Option<String>,
+}
+
+async fn fetch_manifest(
+    http_client: &reqwest::Client,
+    channel: &Channel,
+    primary_dist_server: &Url,
+) -> anyhow::Result<Manifest> {
+    let url_string = channel.manifest_url(primary_dist_server);
+    let url = Url::parse(&url_string)?;
+    let resp = http_client.get(url).send().await?;
+    let status = resp.status();
+    if !status.is_success() {
+        bail!("{} {}", status, url_string);
+    }
+    let text = resp.text().await?;
+    Ok(toml::from_str(&text)?)
+}
+
+fn manifest_archive<'m>(manifest: &'m Manifest, install: &Install) -> anyhow::Result<&'m ManifestTarget> {
+    let target_key = install.pkg.manifest_target_key(&install.target);
+    let archive = manifest
+        .pkg
+        .get(install.pkg.manifest_name())
+        .and_then(|package| package.target.get(target_key))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "manifest has no {:?} package for target {}",
+                install.pkg.name(),
+                install.target,
+            )
+        })?;
+    if !archive.available {
+        bail!(
+            "{:?} is not available for target {} on this channel",
+            install.pkg.name(),
+            install.target,
+        );
+    }
+    Ok(archive)
+}
+
+/// Picks which archive variant to fetch for an install, honoring the
+/// user's `--compression` preference. `Auto` prefers zstd, since it
+/// decodes noticeably faster than xz for the largest components
+/// (rustc, rust-std) and the manifest only advertises it when available --
+/// today that's nowhere: the real channel manifest never populates
+/// `zst_url`/`zst_hash` for any package, so `Auto` currently always falls
+/// back to xz and `--compression zstd` always errors. This is dead code
+/// until the dist server actually starts publishing zstd archives; it isn't
+/// a throughput win yet.
+fn select_archive<'m>(
+    archive: &'m ManifestTarget,
+    pkg: Pkg,
+    target: &str,
+    preference: CompressionPref,
+) -> anyhow::Result<(Compression, &'m str, &'m str)> {
+    let want_zstd = match preference {
+        CompressionPref::Zstd => true,
+        CompressionPref::Xz => false,
+        CompressionPref::Auto => archive.zst_url.is_some(),
+    };
+    if want_zstd {
+        let url = archive.zst_url.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("manifest has no zstd archive for {:?} target {target}", pkg.name())
+        })?;
+        let hash = archive.zst_hash.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("manifest has no zstd hash for {:?} target {target}", pkg.name())
+        })?;
+        Ok((Compression::Zstd, url, hash))
+    } else {
+        let url = archive.xz_url.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("manifest has no xz archive for {:?} target {target}", pkg.name())
+        })?;
+        let hash = archive.xz_hash.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("manifest has no xz hash for {:?} target {target}", pkg.name())
+        })?;
+        Ok((Compression::Xz, url, hash))
+    }
+}
+
 struct Chunks {
     cur: bytes::buf::Reader<Bytes>,
     rest: UnboundedReceiver<Bytes>,
@@ -150,46 +686,599 @@ impl io::Read for Chunks {
     }
 }
 
-async fn do_install(thread_pool: ThreadPool, date: &str, root: &Path) -> anyhow::Result<()> {
-    let (complete_sender, mut complete_receiver) = mpsc::unbounded_channel();
-    let mut task_handles = Vec::new();
+/// Wraps a reader and feeds every byte that passes through it into a
+/// running SHA-256 hash, so that verifying a download's integrity costs no
+/// extra pass over the data: hashing happens inline with decompression.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
 
-    let http_client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
-    let http_client = Arc::new(http_client);
+impl<R> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+}
+
+impl<R: io::Read> io::Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
+
+/// One breaker per configured dist-server, so that a dead mirror trips only
+/// its own breaker instead of poisoning every other mirror's downloads too:
+/// `download_component_with_failover` indexes into the shared `Vec` by
+/// mirror, and the handful of retries one component burns through against a
+/// down primary (well within `RETRY_MAX_ATTEMPTS_WITH_FAILOVER`, let alone
+/// `RETRY_MAX_ATTEMPTS`) can never stop a healthy secondary from being tried.
+struct CircuitBreaker {
+    consecutive_failures: AtomicUsize,
+}
+
+impl CircuitBreaker {
+    fn new() -> Self {
+        CircuitBreaker {
+            consecutive_failures: AtomicUsize::new(0),
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.consecutive_failures.load(Ordering::SeqCst) >= CIRCUIT_BREAKER_THRESHOLD
+    }
+
+    fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::SeqCst);
+    }
+
+    fn record_failure(&self) {
+        self.consecutive_failures.fetch_add(1, Ordering::SeqCst);
+    }
+}
 
-    for component in COMPONENTS {
-        let url_string = format!(
-            "{RUSTUP_DIST_SERVER}/dist/{date}/{archive}",
-            archive = component.archive,
+/// Tracks overall install progress for the `--status-socket` `Progress`
+/// event. When every archive's size was discoverable up front (a `HEAD`
+/// request per archive succeeded with a `Content-Length`), progress is
+/// weighted by bytes downloaded across all archives; the channel manifest
+/// itself doesn't declare archive sizes, and some mirrors don't answer
+/// `HEAD` either, so when any size is unknown this falls back to the
+/// coarser completed/total component count instead of guessing.
+enum ProgressTracker {
+    ByBytes {
+        downloaded: AtomicU64,
+        total: u64,
+    },
+    ByComponentCount {
+        completed: AtomicUsize,
+        total: usize,
+    },
+}
+
+impl ProgressTracker {
+    /// Call once per chunk downloaded. Returns the new overall percentage
+    /// when tracking by bytes, or `None` when tracking by component count
+    /// (in which case `record_component_complete` is what reports progress).
+    fn record_bytes(&self, n: u64) -> Option<f64> {
+        match self {
+            ProgressTracker::ByBytes { downloaded, total } => {
+                let now = downloaded.fetch_add(n, Ordering::SeqCst) + n;
+                Some(100.0 * now as f64 / *total as f64)
+            }
+            ProgressTracker::ByComponentCount { .. } => None,
+        }
+    }
+
+    /// Call once per component that finishes extracting and verifying.
+    /// Returns the new overall percentage when tracking by component count,
+    /// or `None` when tracking by bytes (already reported per chunk).
+    fn record_component_complete(&self) -> Option<f64> {
+        match self {
+            ProgressTracker::ByComponentCount { completed, total } => {
+                let now = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                Some(100.0 * now as f64 / *total as f64)
+            }
+            ProgressTracker::ByBytes { .. } => None,
+        }
+    }
+}
+
+/// Issues a `HEAD` request to learn an archive's size up front, so overall
+/// progress can be weighted by bytes instead of component count. Returns
+/// `None` if the request fails, times out, or the response has no
+/// `Content-Length` -- a size probe is a nice-to-have, never worth blocking
+/// the actual download on, so any of those just falls back to coarser
+/// progress tracking for the whole install.
+async fn fetch_archive_size(
+    http_client: &reqwest::Client,
+    dist_server: &Url,
+    archive_path: &str,
+) -> Option<u64> {
+    let url_string = format!("{}{archive_path}", dist_server.as_str().trim_end_matches('/'));
+    let url = Url::parse(&url_string).ok()?;
+    let resp = tokio::time::timeout(SIZE_PROBE_TIMEOUT, http_client.head(url).send())
+        .await
+        .ok()?
+        .ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    resp.content_length()
+}
+
+/// Whether a failed download attempt is worth retrying. Connection resets,
+/// timeouts, and 5xx responses are assumed transient; 4xx responses (a bad
+/// URL, an archive that really doesn't exist) will never succeed on retry.
+enum DownloadError {
+    Fatal(anyhow::Error),
+    Retryable(anyhow::Error),
+}
+
+fn classify_reqwest_error(err: reqwest::Error) -> DownloadError {
+    if err.is_timeout() || err.is_connect() || err.is_body() {
+        DownloadError::Retryable(err.into())
+    } else {
+        DownloadError::Fatal(err.into())
+    }
+}
+
+/// Backoff with full jitter: picks a uniformly random delay between zero
+/// and `min(base * 2^attempt, cap)`, which spreads out retries from the
+/// concurrent component tasks instead of having them all hammer the server in lockstep.
+fn backoff_with_jitter(attempt: u32) -> Duration {
+    let exp = RETRY_BASE_DELAY.saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX));
+    let capped = exp.min(RETRY_MAX_DELAY);
+    Duration::from_millis(fastrand::u64(0..=capped.as_millis() as u64))
+}
+
+/// Parses the start offset out of a `Content-Range: bytes <start>-<end>/<size>`
+/// response header, returning `None` if it's absent or not in that form.
+fn content_range_start(resp: &reqwest::Response) -> Option<u64> {
+    let value = resp.headers().get(reqwest::header::CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.strip_prefix("bytes ")?;
+    let (start, _) = range.split_once('-')?;
+    start.parse().ok()
+}
+
+/// One HTTP attempt at downloading `url`, resuming from `resume_from` bytes
+/// in via `Range` if we've made partial progress on an earlier attempt.
+/// Always returns the number of bytes forwarded to `chunk_sender` during
+/// this attempt, even on failure, so the caller can track the resume point.
+async fn download_attempt(
+    http_client: &reqwest::Client,
+    url: &Url,
+    url_string: &str,
+    resume_from: u64,
+    chunk_sender: &mpsc::UnboundedSender<Bytes>,
+    status_socket: Option<&StatusSocket>,
+    component: &str,
+    bytes_forwarded: &AtomicU64,
+    tracker: &ProgressTracker,
+) -> (u64, Result<(), DownloadError>) {
+    let mut req = http_client.get(url.clone());
+    if resume_from > 0 {
+        req = req.header(reqwest::header::RANGE, format!("bytes={resume_from}-"));
+    }
+
+    let mut resp = match req.send().await {
+        Ok(resp) => resp,
+        Err(err) => return (0, Err(classify_reqwest_error(err))),
+    };
+
+    let resp_status = resp.status();
+    if resume_from > 0 && resp_status == reqwest::StatusCode::OK {
+        // The server ignored our Range header and is about to resend the
+        // whole file from the start; resuming would duplicate bytes ahead
+        // of the decoder, so treat this as unrecoverable.
+        return (
+            0,
+            Err(DownloadError::Fatal(anyhow::anyhow!(
+                "{resp_status} {url_string}: server does not support resuming with Range",
+            ))),
         );
+    }
+    if resume_from > 0 && resp_status == reqwest::StatusCode::PARTIAL_CONTENT {
+        // A 206 is only trustworthy if the server actually resumed at the
+        // offset we asked for; a caching proxy that returns 206 but restarts
+        // the body at byte 0 (or any offset other than resume_from) would
+        // feed duplicated bytes into the decoder just like the OK case above.
+        match content_range_start(&resp) {
+            Some(start) if start == resume_from => {}
+            Some(start) => {
+                return (
+                    0,
+                    Err(DownloadError::Fatal(anyhow::anyhow!(
+                        "{resp_status} {url_string}: server resumed at byte {start}, \
+                         not the requested {resume_from}",
+                    ))),
+                );
+            }
+            None => {
+                return (
+                    0,
+                    Err(DownloadError::Fatal(anyhow::anyhow!(
+                        "{resp_status} {url_string}: resumed response is missing a \
+                         usable Content-Range header",
+                    ))),
+                );
+            }
+        }
+    }
+    if !resp_status.is_success() {
+        let err = anyhow::anyhow!("{resp_status} {url_string}");
+        return (
+            0,
+            Err(if resp_status.is_client_error() {
+                DownloadError::Fatal(err)
+            } else {
+                DownloadError::Retryable(err)
+            }),
+        );
+    }
+
+    let mut received = 0u64;
+    loop {
+        match resp.chunk().await {
+            Ok(Some(chunk)) => {
+                let chunk_len = chunk.len() as u64;
+                received += chunk_len;
+                if let Some(status_socket) = status_socket {
+                    status_socket.emit(&StatusEvent::Downloaded {
+                        component: component.to_owned(),
+                        bytes_downloaded: resume_from + received,
+                    });
+                }
+                if chunk_sender.send(chunk).is_err() {
+                    break;
+                }
+                bytes_forwarded.fetch_add(chunk_len, Ordering::SeqCst);
+                if let Some(percent) = tracker.record_bytes(chunk_len) {
+                    if let Some(status_socket) = status_socket {
+                        status_socket.emit(&StatusEvent::Progress { percent });
+                    }
+                }
+            }
+            Ok(None) => break,
+            Err(err) => return (received, Err(classify_reqwest_error(err))),
+        }
+    }
+    (received, Ok(()))
+}
+
+async fn download_component(
+    http_client: Arc<reqwest::Client>,
+    url: Url,
+    url_string: String,
+    chunk_sender: &mpsc::UnboundedSender<Bytes>,
+    breaker: &CircuitBreaker,
+    max_attempts: u32,
+    status_socket: Option<&StatusSocket>,
+    component: &str,
+    bytes_forwarded: &AtomicU64,
+    tracker: &ProgressTracker,
+) -> anyhow::Result<()> {
+    let mut bytes_sent = 0u64;
+    for attempt in 0..max_attempts {
+        if breaker.is_open() {
+            bail!("circuit breaker open after repeated failures; giving up on {url_string}");
+        }
+
+        let (received, result) = download_attempt(
+            &http_client,
+            &url,
+            &url_string,
+            bytes_sent,
+            chunk_sender,
+            status_socket,
+            component,
+            bytes_forwarded,
+            tracker,
+        )
+        .await;
+        bytes_sent += received;
+
+        match result {
+            Ok(()) => {
+                breaker.record_success();
+                return Ok(());
+            }
+            Err(DownloadError::Fatal(err)) => {
+                // Fatal errors (a 4xx, or a Range resume the server botched)
+                // are by definition not retried and say nothing about the
+                // server's health, so they shouldn't count toward tripping
+                // the breaker -- only a run of `Retryable` failures should.
+                return Err(err);
+            }
+            Err(DownloadError::Retryable(err)) => {
+                breaker.record_failure();
+                if attempt + 1 == max_attempts {
+                    return Err(err.context(format!(
+                        "giving up on {url_string} after {max_attempts} attempts",
+                    )));
+                }
+                let delay = backoff_with_jitter(attempt);
+                let _ = writeln!(
+                    io::stderr(),
+                    "retrying {url_string} in {delay:.02?} after error: {err:#}",
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+    unreachable!("loop above always returns before exhausting max_attempts attempts")
+}
+
+/// Runs `download_component` against `dist_servers[start_mirror]`, and on
+/// failure, tries the remaining mirrors in round-robin order before giving
+/// up. Failover is only safe before any bytes have reached the decoder: once
+/// `download_component` has forwarded part of an archive to `chunk_sender`,
+/// the tar/decompression pipeline is mid-stream and switching to a different
+/// source (even one serving byte-identical content) would interleave two
+/// unrelated streams, so in that case the error is propagated immediately
+/// instead of retried on a different mirror.
+///
+/// Each mirror gets its own entry in `breakers` (one breaker per
+/// `dist_servers` index) so a dead mirror can never stop a healthy one from
+/// being tried. When more than one mirror is configured, `download_component`
+/// is also capped at `RETRY_MAX_ATTEMPTS_WITH_FAILOVER` attempts instead of
+/// the full `RETRY_MAX_ATTEMPTS`, so a dead mirror is abandoned quickly
+/// rather than delaying failover by the full backoff schedule.
+async fn download_component_with_failover(
+    http_client: Arc<reqwest::Client>,
+    dist_servers: &[Url],
+    start_mirror: usize,
+    archive_path: &str,
+    chunk_sender: mpsc::UnboundedSender<Bytes>,
+    breakers: Arc<Vec<CircuitBreaker>>,
+    status_socket: Option<Arc<StatusSocket>>,
+    component: String,
+    tracker: Arc<ProgressTracker>,
+) -> anyhow::Result<()> {
+    let max_attempts = if dist_servers.len() > 1 {
+        RETRY_MAX_ATTEMPTS_WITH_FAILOVER
+    } else {
+        RETRY_MAX_ATTEMPTS
+    };
+    let mut last_err = None;
+    for offset in 0..dist_servers.len() {
+        let mirror_index = (start_mirror + offset) % dist_servers.len();
+        let dist_server = &dist_servers[mirror_index];
+        let url_string = format!("{}{archive_path}", dist_server.as_str().trim_end_matches('/'));
         let url = Url::parse(&url_string)?;
+        let bytes_forwarded = AtomicU64::new(0);
+
+        match download_component(
+            Arc::clone(&http_client),
+            url,
+            url_string.clone(),
+            &chunk_sender,
+            &breakers[mirror_index],
+            max_attempts,
+            status_socket.as_deref(),
+            &component,
+            &bytes_forwarded,
+            &tracker,
+        )
+        .await
+        {
+            Ok(()) => return Ok(()),
+            Err(err) if bytes_forwarded.load(Ordering::SeqCst) == 0 && offset + 1 < dist_servers.len() => {
+                let _ = writeln!(
+                    io::stderr(),
+                    "{url_string} failed before any bytes were forwarded, trying next mirror: {err:#}",
+                );
+                last_err = Some(err);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("no dist-server mirrors configured")))
+}
+
+/// Extracts every component into a staging directory next to `root` and
+/// only commits it to `root` once every component has verified successfully,
+/// so a checksum mismatch or a download that never completes can never leave
+/// a truncated toolchain at `root` for `root.try_exists()` to mistake for a
+/// complete install on the next run. If downloading, extracting or verifying
+/// fails, the staging directory is removed and `root` is left exactly as it
+/// was found; an error while merging into an existing `root` is returned
+/// as is, leaving both directories as far as the merge got.
+async fn do_install(
+    thread_pool: ThreadPool,
+    channel: &Channel,
+    installs: Vec<Install>,
+    compression: CompressionPref,
+    status_socket: Option<StatusSocketAddr>,
+    dist_servers: Vec<Url>,
+    root: &Path,
+) -> anyhow::Result<()> {
+    let staging_root = staging_path_for(root);
+    let _ = fs::remove_dir_all(&staging_root);
+    fs::create_dir(&staging_root)?;
+
+    let result = do_install_to(
+        thread_pool,
+        channel,
+        installs,
+        compression,
+        status_socket,
+        dist_servers,
+        &staging_root,
+    )
+    .await;
+
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging_root);
+        return result;
+    }
+
+    if root.try_exists()? {
+        merge_dir_into(&staging_root, root)?;
+        fs::remove_dir_all(&staging_root)?;
+    } else {
+        fs::rename(&staging_root, root)?;
+    }
+
+    Ok(())
+}
+
+async fn do_install_to(
+    thread_pool: ThreadPool,
+    channel: &Channel,
+    installs: Vec<Install>,
+    compression: CompressionPref,
+    status_socket: Option<StatusSocketAddr>,
+    dist_servers: Vec<Url>,
+    root: &Path,
+) -> anyhow::Result<()> {
+    let http_client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+    let http_client = Arc::new(http_client);
+
+    let manifest = fetch_manifest(&http_client, channel, &dist_servers[0]).await?;
+    let breakers = Arc::new(dist_servers.iter().map(|_| CircuitBreaker::new()).collect::<Vec<_>>());
+    let status_socket = match status_socket {
+        Some(addr) => Some(bind_status_socket(addr).await?),
+        None => None,
+    };
+
+    // Resolve each install's archive up front (still sequentially, before
+    // anything is spawned) both to fail fast on a bad manifest entry and to
+    // have every archive_path in hand for the size probe below.
+    struct InstallPlan {
+        archive_compression: Compression,
+        archive_path: String,
+        expected_hash: String,
+        subdir: String,
+        component: String,
+        start_mirror: usize,
+    }
+
+    let mut plans = Vec::with_capacity(installs.len());
+    for (index, install) in installs.iter().enumerate() {
+        let archive = manifest_archive(&manifest, install)?;
+        let (archive_compression, url_string, expected_hash) =
+            select_archive(archive, install.pkg, &install.target, compression)?;
+        // The manifest declares a full URL (normally pointing at the
+        // upstream dist server); we keep only its path and re-root it onto
+        // whichever mirror this component is assigned to, per-component
+        // URLs are always `{dist_server}/dist/{date}/{archive}`.
+        let archive_path = Url::parse(url_string)?.path().to_owned();
+        plans.push(InstallPlan {
+            archive_compression,
+            archive_path,
+            expected_hash: expected_hash.to_owned(),
+            subdir: install.subdir.clone(),
+            component: install.subdir.clone(),
+            start_mirror: index % dist_servers.len(),
+        });
+    }
+
+    // A HEAD request per archive to learn its size, so overall progress can
+    // be weighted by bytes instead of the coarser completed/total component
+    // count; falls back to the latter if any archive's size can't be found
+    // this way (the manifest itself never declares sizes). Nothing consumes
+    // byte-weighted progress without a `--status-socket` client to report it
+    // to, so skip the probing round trips entirely on the default, socket-less
+    // path and go straight to component-count tracking. Run every probe
+    // concurrently rather than one at a time, so a single slow or wedged
+    // mirror only costs its own timeout instead of serializing in front of
+    // every other component's probe (and every component's download, since
+    // no download starts until the whole size-tracking decision is made).
+    let tracker = Arc::new(if status_socket.is_some() {
+        let size_handles: Vec<_> = plans
+            .iter()
+            .map(|plan| {
+                let http_client = Arc::clone(&http_client);
+                let dist_server = dist_servers[plan.start_mirror].clone();
+                let archive_path = plan.archive_path.clone();
+                tokio::spawn(async move { fetch_archive_size(&http_client, &dist_server, &archive_path).await })
+            })
+            .collect();
+        let mut sizes = Vec::with_capacity(size_handles.len());
+        for handle in size_handles {
+            sizes.push(handle.await.unwrap_or(None));
+        }
+        match sizes.into_iter().collect::<Option<Vec<u64>>>() {
+            Some(sizes) => ProgressTracker::ByBytes {
+                downloaded: AtomicU64::new(0),
+                total: sizes.iter().sum(),
+            },
+            None => ProgressTracker::ByComponentCount {
+                completed: AtomicUsize::new(0),
+                total: plans.len(),
+            },
+        }
+    } else {
+        ProgressTracker::ByComponentCount {
+            completed: AtomicUsize::new(0),
+            total: plans.len(),
+        }
+    });
+
+    let (complete_sender, mut complete_receiver) = mpsc::unbounded_channel();
+    let mut task_handles = Vec::new();
+
+    for plan in plans {
+        let InstallPlan {
+            archive_compression,
+            archive_path,
+            expected_hash,
+            subdir,
+            component,
+            start_mirror,
+        } = plan;
 
         let (chunk_sender, chunk_receiver) = mpsc::unbounded_channel();
 
         task_handles.push(tokio::spawn({
             let http_client = Arc::clone(&http_client);
+            let breakers = Arc::clone(&breakers);
+            let status_socket = status_socket.clone();
+            let component = component.clone();
+            let dist_servers = dist_servers.clone();
+            let tracker = Arc::clone(&tracker);
             async move {
-                let req = http_client.get(url);
-                let mut resp = req.send().await?;
-                let status = resp.status();
-                if !status.is_success() {
-                    bail!("{} {}", status, url_string);
-                }
-                while let Some(chunk) = resp.chunk().await? {
-                    if chunk_sender.send(chunk).is_err() {
-                        break;
-                    }
-                }
-                drop(chunk_sender);
-                Ok(())
+                download_component_with_failover(
+                    http_client,
+                    &dist_servers,
+                    start_mirror,
+                    &archive_path,
+                    chunk_sender,
+                    breakers,
+                    status_socket,
+                    component,
+                    tracker,
+                )
+                .await
             }
         }));
 
         thread_pool.spawn({
             let root = root.to_owned();
             let complete_sender = complete_sender.clone();
+            let status_socket = status_socket.clone();
             move || {
-                let result = do_extract(&root, chunk_receiver, component.subdir);
+                let result = do_extract(
+                    &root,
+                    chunk_receiver,
+                    subdir,
+                    archive_compression,
+                    expected_hash,
+                    status_socket,
+                    component,
+                );
                 let _ = complete_sender.send(result);
             }
         });
@@ -199,6 +1288,11 @@ async fn do_install(thread_pool: ThreadPool, date: &str, root: &Path) -> anyhow:
 
     while let Some(result) = complete_receiver.recv().await {
         () = result?;
+        if let Some(percent) = tracker.record_component_complete() {
+            if let Some(status_socket) = &status_socket {
+                status_socket.emit(&StatusEvent::Progress { percent });
+            }
+        }
     }
 
     for task_handle in task_handles {
@@ -208,14 +1302,70 @@ async fn do_install(thread_pool: ThreadPool, date: &str, root: &Path) -> anyhow:
     Ok(())
 }
 
+/// The two archive formats we know how to decode: xz, which we've always
+/// supported and which the dist server actually publishes today, and zstd,
+/// which would decode noticeably faster for the largest components (rustc,
+/// rust-std) at a comparable download size if the dist server published it
+/// -- see the caveat on `select_archive`, this path is currently unreachable
+/// in practice.
+///
+/// Decoding a single zstd frame is inherently sequential (each block can
+/// reference back into the window built up by the ones before it), so
+/// there's no block-level decode parallelism to exploit within one
+/// archive the way there is for multithreaded zstd *compression*. The
+/// parallelism we do get is across archives: `do_install_to` spawns one
+/// `do_extract` per component onto the rayon pool, which has one thread
+/// per component up to the number of available cores, so components
+/// decode concurrently regardless of format.
+enum ArchiveDecoder<R: io::Read> {
+    Xz(xz2::read::XzDecoder<R>),
+    Zstd(zstd::stream::read::Decoder<'static, io::BufReader<R>>),
+}
+
+impl<R: io::Read> ArchiveDecoder<R> {
+    fn new(compression: Compression, reader: R) -> anyhow::Result<Self> {
+        Ok(match compression {
+            Compression::Xz => ArchiveDecoder::Xz(xz2::read::XzDecoder::new(reader)),
+            Compression::Zstd => ArchiveDecoder::Zstd(zstd::stream::read::Decoder::new(reader)?),
+        })
+    }
+
+    fn into_inner(self) -> R {
+        match self {
+            ArchiveDecoder::Xz(decoder) => decoder.into_inner(),
+            ArchiveDecoder::Zstd(decoder) => decoder.finish().into_inner(),
+        }
+    }
+}
+
+impl<R: io::Read> io::Read for ArchiveDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ArchiveDecoder::Xz(decoder) => decoder.read(buf),
+            ArchiveDecoder::Zstd(decoder) => decoder.read(buf),
+        }
+    }
+}
+
 fn do_extract(
     root: &Path,
     receiver: UnboundedReceiver<Bytes>,
-    subdir: &'static str,
+    subdir: String,
+    compression: Compression,
+    expected_hash: String,
+    status_socket: Option<Arc<StatusSocket>>,
+    component_label: String,
 ) -> anyhow::Result<()> {
+    if let Some(status_socket) = &status_socket {
+        status_socket.emit(&StatusEvent::ExtractionStarted {
+            component: component_label.clone(),
+        });
+    }
+
     let chunks = Chunks::new(receiver);
-    let xz = xz2::read::XzDecoder::new(chunks);
-    let mut archive = tar::Archive::new(xz);
+    let hashing = HashingReader::new(chunks);
+    let decoder = ArchiveDecoder::new(compression, hashing)?;
+    let mut archive = tar::Archive::new(decoder);
     for entry in archive.entries()? {
         let mut entry = entry?;
         let header = entry.header();
@@ -225,7 +1375,7 @@ fn do_extract(
             continue;
         }
         match components.next() {
-            Some(component) if component.as_os_str() == subdir => {}
+            Some(component) if component.as_os_str() == subdir.as_str() => {}
             _ => continue,
         }
         if components.as_path().as_os_str() == "manifest.in" {
@@ -242,5 +1392,218 @@ fn do_extract(
             entry.unpack(target)?;
         }
     }
+
+    // Drain whatever tail of the compressed stream `tar` didn't need to
+    // read (e.g. trailing padding past the end-of-archive marker) so the
+    // hash below covers the entire downloaded file, matching the
+    // manifest's hash for whichever archive variant we fetched.
+    let mut decoder = archive.into_inner();
+    io::copy(&mut decoder, &mut io::sink())?;
+    let digest = hex_encode(&decoder.into_inner().hasher.finalize());
+    if digest != expected_hash {
+        bail!(
+            "checksum mismatch for {subdir}: expected {expected_hash}, got {digest}",
+        );
+    }
+
+    if let Some(status_socket) = &status_socket {
+        status_socket.emit(&StatusEvent::ExtractionFinished {
+            component: component_label,
+        });
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(pkg: Pkg, target: &str) -> Install {
+        Install {
+            pkg,
+            target: target.to_owned(),
+            subdir: pkg.subdir(target),
+        }
+    }
+
+    /// Builds a single-package manifest with `target` filed under the real
+    /// manifest key for `pkg` (`manifest_name()`) and the real target key
+    /// (`manifest_target_key()`, the literal `"*"` for `rust-src`, otherwise
+    /// `at_target`), matching the schema the actual dist server publishes
+    /// rather than whatever key the code happens to look under.
+    fn manifest_with(pkg: Pkg, at_target: &str, target: ManifestTarget) -> Manifest {
+        let mut package_targets = BTreeMap::new();
+        package_targets.insert(pkg.manifest_target_key(at_target).to_owned(), target);
+        let mut pkgs = BTreeMap::new();
+        pkgs.insert(pkg.manifest_name().to_owned(), ManifestPackage { target: package_targets });
+        Manifest { pkg: pkgs }
+    }
+
+    #[test]
+    fn manifest_archive_rejects_unavailable_target() {
+        let manifest = manifest_with(
+            Pkg::RustDocs,
+            TARGET,
+            ManifestTarget {
+                available: false,
+                xz_url: None,
+                xz_hash: None,
+                zst_url: None,
+                zst_hash: None,
+            },
+        );
+        let err = manifest_archive(&manifest, &install(Pkg::RustDocs, TARGET)).unwrap_err();
+        assert!(err.to_string().contains("not available"), "{err}");
+    }
+
+    #[test]
+    fn manifest_archive_rejects_missing_target() {
+        let manifest = manifest_with(
+            Pkg::RustDocs,
+            TARGET,
+            ManifestTarget {
+                available: true,
+                xz_url: Some("https://example.com/a.tar.xz".to_owned()),
+                xz_hash: Some("deadbeef".to_owned()),
+                zst_url: None,
+                zst_hash: None,
+            },
+        );
+        let err = manifest_archive(&manifest, &install(Pkg::RustDocs, "made-up-target")).unwrap_err();
+        assert!(err.to_string().contains("no"), "{err}");
+    }
+
+    #[test]
+    fn manifest_archive_looks_up_rust_src_under_the_wildcard_target_key() {
+        let manifest = manifest_with(
+            Pkg::RustSrc,
+            "*",
+            ManifestTarget {
+                available: true,
+                xz_url: Some("https://example.com/rust-src.tar.xz".to_owned()),
+                xz_hash: Some("deadbeef".to_owned()),
+                zst_url: None,
+                zst_hash: None,
+            },
+        );
+        // `install.target` is still the host triple (rust-src isn't built
+        // per-target), but the manifest files it under `"*"` regardless.
+        manifest_archive(&manifest, &install(Pkg::RustSrc, TARGET)).unwrap();
+    }
+
+    #[test]
+    fn manifest_archive_looks_up_clippy_and_rustfmt_under_their_preview_keys() {
+        for pkg in [Pkg::Clippy, Pkg::Rustfmt] {
+            let manifest = manifest_with(
+                pkg,
+                TARGET,
+                ManifestTarget {
+                    available: true,
+                    xz_url: Some("https://example.com/a.tar.xz".to_owned()),
+                    xz_hash: Some("deadbeef".to_owned()),
+                    zst_url: None,
+                    zst_hash: None,
+                },
+            );
+            manifest_archive(&manifest, &install(pkg, TARGET)).unwrap();
+            assert_ne!(pkg.name(), pkg.manifest_name());
+            assert!(!manifest.pkg.contains_key(pkg.name()));
+        }
+    }
+
+    #[test]
+    fn select_archive_xz_preference_requires_xz_fields() {
+        let archive = ManifestTarget {
+            available: true,
+            xz_url: None,
+            xz_hash: None,
+            zst_url: Some("https://example.com/a.tar.zst".to_owned()),
+            zst_hash: Some("deadbeef".to_owned()),
+        };
+        let err = select_archive(&archive, Pkg::RustSrc, TARGET, CompressionPref::Xz).unwrap_err();
+        assert!(err.to_string().contains("no xz archive"), "{err}");
+    }
+
+    #[test]
+    fn select_archive_auto_falls_back_to_xz_without_zstd() {
+        let archive = ManifestTarget {
+            available: true,
+            xz_url: Some("https://example.com/a.tar.xz".to_owned()),
+            xz_hash: Some("deadbeef".to_owned()),
+            zst_url: None,
+            zst_hash: None,
+        };
+        let (compression, url, hash) =
+            select_archive(&archive, Pkg::RustSrc, TARGET, CompressionPref::Auto).unwrap();
+        assert!(matches!(compression, Compression::Xz));
+        assert_eq!(url, "https://example.com/a.tar.xz");
+        assert_eq!(hash, "deadbeef");
+    }
+
+    #[test]
+    fn select_archive_auto_prefers_zstd_when_available() {
+        let archive = ManifestTarget {
+            available: true,
+            xz_url: Some("https://example.com/a.tar.xz".to_owned()),
+            xz_hash: Some("deadbeef".to_owned()),
+            zst_url: Some("https://example.com/a.tar.zst".to_owned()),
+            zst_hash: Some("feedface".to_owned()),
+        };
+        let (compression, url, hash) =
+            select_archive(&archive, Pkg::RustSrc, TARGET, CompressionPref::Auto).unwrap();
+        assert!(matches!(compression, Compression::Zstd));
+        assert_eq!(url, "https://example.com/a.tar.zst");
+        assert_eq!(hash, "feedface");
+    }
+
+    #[test]
+    fn channel_parse_nightly_date() {
+        assert!(matches!(Channel::parse("nightly-2024-01-01"), Ok(Channel::Nightly(date)) if date == "2024-01-01"));
+        assert!(Channel::parse("nightly-2024-1-1").is_err());
+        assert!(Channel::parse("nightly-bogus").is_err());
+    }
+
+    #[test]
+    fn channel_parse_named_and_version_channels() {
+        assert!(matches!(Channel::parse("beta"), Ok(Channel::Beta)));
+        assert!(matches!(Channel::parse("stable"), Ok(Channel::Stable)));
+        assert!(matches!(Channel::parse("1.75.0"), Ok(Channel::Version(v)) if v == "1.75.0"));
+        assert!(Channel::parse("1.75").is_err());
+        assert!(Channel::parse("garbage").is_err());
+    }
+
+    #[test]
+    fn backoff_with_jitter_stays_within_the_capped_window() {
+        for attempt in 0..12 {
+            let delay = backoff_with_jitter(attempt);
+            assert!(delay <= RETRY_MAX_DELAY, "attempt {attempt}: {delay:?} exceeds cap");
+        }
+    }
+
+    fn response_with_content_range(value: Option<&str>) -> reqwest::Response {
+        let mut builder = http::Response::builder().status(206);
+        if let Some(value) = value {
+            builder = builder.header(reqwest::header::CONTENT_RANGE, value);
+        }
+        reqwest::Response::from(builder.body(Vec::new()).unwrap())
+    }
+
+    #[test]
+    fn content_range_start_parses_the_start_offset() {
+        let resp = response_with_content_range(Some("bytes 1024-2047/4096"));
+        assert_eq!(content_range_start(&resp), Some(1024));
+    }
+
+    #[test]
+    fn content_range_start_is_none_without_the_header() {
+        let resp = response_with_content_range(None);
+        assert_eq!(content_range_start(&resp), None);
+    }
+
+    #[test]
+    fn content_range_start_is_none_for_malformed_values() {
+        assert_eq!(content_range_start(&response_with_content_range(Some("bytes */4096"))), None);
+        assert_eq!(content_range_start(&response_with_content_range(Some("1024-2047/4096"))), None);
+    }
+}